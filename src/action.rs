@@ -0,0 +1,162 @@
+use clap::ValueEnum;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+/// What to do with the losing copy once a duplicate pair has been resolved.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    /// Just print which copy lost, same as bkgrep's original behaviour
+    Print,
+    /// Move the losing copy into --trash-dir
+    Trash,
+    /// Delete the losing copy outright
+    Delete,
+    /// Replace the losing copy with a hardlink to the surviving copy
+    Hardlink,
+}
+
+/// Runtime configuration for what happens to a losing duplicate.
+pub struct ActionConfig {
+    pub action: Action,
+    pub trash_dir: String,
+    pub confirm: bool,
+}
+
+/// Act on the losing copy of a duplicate pair: `winner` survives, `loser` is
+/// printed and then, depending on `config.action`, trashed, deleted or
+/// replaced with a hardlink to `winner`. Destructive actions are refused
+/// unless `config.confirm` is set, so `--action trash` alone is a dry run.
+pub fn resolve_duplicate(
+    winner: &str,
+    loser: &str,
+    config: &ActionConfig,
+) -> Result<(), Box<dyn Error>> {
+    println!("DUP:{}", loser);
+
+    if config.action == Action::Print {
+        return Ok(());
+    }
+    if !config.confirm {
+        eprintln!(
+            "Skipping --action {:?} for {}: pass --confirm to actually touch files",
+            config.action, loser
+        );
+        return Ok(());
+    }
+
+    match config.action {
+        Action::Print => unreachable!(),
+        Action::Trash => {
+            fs::create_dir_all(&config.trash_dir)?;
+            let name = Path::new(loser)
+                .file_name()
+                .ok_or("duplicate path has no file name")?;
+            fs::rename(loser, Path::new(&config.trash_dir).join(name))?;
+        }
+        Action::Delete => fs::remove_file(loser)?,
+        Action::Hardlink => {
+            // Link to a sibling temp name first and rename it over loser only
+            // once that succeeds - hard_link can fail (eg. winner and loser
+            // on different filesystems), and removing loser beforehand would
+            // leave nothing behind to replace it.
+            let tmp = Path::new(loser).with_extension("bkgrep-tmp-hardlink");
+            fs::hard_link(winner, &tmp)?;
+            fs::rename(&tmp, loser)?;
+        }
+    }
+    Ok(())
+}
+
+/// A scratch directory under the OS temp dir, unique per test invocation.
+#[cfg(test)]
+fn test_dir(name: &str) -> std::path::PathBuf {
+    use std::sync::atomic::{AtomicU64, Ordering};
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let n = COUNTER.fetch_add(1, Ordering::SeqCst);
+    let dir = std::env::temp_dir().join(format!("bkgrep-test-{}-{}-{}", std::process::id(), name, n));
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[cfg(test)]
+fn write_book(dir: &Path, name: &str, contents: &[u8]) -> String {
+    let path = dir.join(name);
+    fs::write(&path, contents).unwrap();
+    path.to_string_lossy().into_owned()
+}
+
+#[test]
+fn print_leaves_both_copies_untouched() {
+    let dir = test_dir("print");
+    let winner = write_book(&dir, "winner.epub", b"winner");
+    let loser = write_book(&dir, "loser.epub", b"loser");
+    let config = ActionConfig {
+        action: Action::Print,
+        trash_dir: dir.join("trash").to_string_lossy().into_owned(),
+        confirm: false,
+    };
+    resolve_duplicate(&winner, &loser, &config).unwrap();
+    assert!(Path::new(&loser).exists());
+}
+
+#[test]
+fn destructive_actions_without_confirm_are_a_dry_run() {
+    let dir = test_dir("noconfirm");
+    let winner = write_book(&dir, "winner.epub", b"winner");
+    let loser = write_book(&dir, "loser.epub", b"loser");
+    let config = ActionConfig {
+        action: Action::Delete,
+        trash_dir: dir.join("trash").to_string_lossy().into_owned(),
+        confirm: false,
+    };
+    resolve_duplicate(&winner, &loser, &config).unwrap();
+    assert!(
+        Path::new(&loser).exists(),
+        "without --confirm the loser must survive"
+    );
+}
+
+#[test]
+fn trash_moves_loser_into_trash_dir() {
+    let dir = test_dir("trash");
+    let winner = write_book(&dir, "winner.epub", b"winner");
+    let loser = write_book(&dir, "loser.epub", b"loser");
+    let trash_dir = dir.join("trash");
+    let config = ActionConfig {
+        action: Action::Trash,
+        trash_dir: trash_dir.to_string_lossy().into_owned(),
+        confirm: true,
+    };
+    resolve_duplicate(&winner, &loser, &config).unwrap();
+    assert!(!Path::new(&loser).exists());
+    assert!(trash_dir.join("loser.epub").exists());
+}
+
+#[test]
+fn delete_removes_loser() {
+    let dir = test_dir("delete");
+    let winner = write_book(&dir, "winner.epub", b"winner");
+    let loser = write_book(&dir, "loser.epub", b"loser");
+    let config = ActionConfig {
+        action: Action::Delete,
+        trash_dir: dir.join("trash").to_string_lossy().into_owned(),
+        confirm: true,
+    };
+    resolve_duplicate(&winner, &loser, &config).unwrap();
+    assert!(!Path::new(&loser).exists());
+}
+
+#[test]
+fn hardlink_replaces_loser_with_link_to_winner() {
+    let dir = test_dir("hardlink");
+    let winner = write_book(&dir, "winner.epub", b"winner");
+    let loser = write_book(&dir, "loser.epub", b"loser");
+    let config = ActionConfig {
+        action: Action::Hardlink,
+        trash_dir: dir.join("trash").to_string_lossy().into_owned(),
+        confirm: true,
+    };
+    resolve_duplicate(&winner, &loser, &config).unwrap();
+    assert_eq!(fs::read(&loser).unwrap(), b"winner");
+}
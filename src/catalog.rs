@@ -0,0 +1,222 @@
+use crate::BookMetadata;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::error::Error;
+use std::sync::Mutex;
+
+/// The subset of a catalog row needed to decide whether a file needs
+/// re-parsing, plus enough metadata to rebuild a `BookMetadata` without
+/// touching the epub again.
+pub struct CatalogRow {
+    pub id: i64,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub publisher: Option<String>,
+    pub creator: Option<String>,
+    pub filesize: i64,
+    pub mtime: i64,
+}
+
+/// Persistent, on-disk index of every book we've ever parsed, so a rescan
+/// only has to touch files that are new or have changed since last time.
+///
+/// `rusqlite::Connection` is `Send` but not `Sync` (its statement cache is a
+/// `RefCell`), and `process_batch` shares one `Catalog` across rayon's
+/// worker threads via `&self`, so the connection has to sit behind a mutex.
+pub struct Catalog {
+    conn: Mutex<Connection>,
+}
+
+impl Catalog {
+    pub fn open(path: &str) -> Result<Self, Box<dyn Error>> {
+        let conn = Connection::open(path)?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS books (
+                file        TEXT PRIMARY KEY,
+                id          INTEGER NOT NULL,
+                title       TEXT,
+                creator     TEXT,
+                publisher   TEXT,
+                description TEXT,
+                filesize    INTEGER NOT NULL,
+                mtime       INTEGER NOT NULL
+            );
+            CREATE VIRTUAL TABLE IF NOT EXISTS books_fts USING fts5(
+                file UNINDEXED,
+                title,
+                creator,
+                description
+            );",
+        )?;
+        Ok(Catalog {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    /// Look up a previously-indexed row for `file`. Callers compare the
+    /// returned `filesize`/`mtime` against the filesystem to decide whether
+    /// the stored metadata is still fresh.
+    pub fn lookup(&self, file: &str) -> Result<Option<CatalogRow>, Box<dyn Error>> {
+        let row = self
+            .conn
+            .lock()
+            .unwrap()
+            .query_row(
+                "SELECT id, title, creator, publisher, description, filesize, mtime
+                 FROM books WHERE file = ?1",
+                params![file],
+                |r| {
+                    Ok(CatalogRow {
+                        id: r.get(0)?,
+                        title: r.get(1)?,
+                        creator: r.get(2)?,
+                        publisher: r.get(3)?,
+                        description: r.get(4)?,
+                        filesize: r.get(5)?,
+                        mtime: r.get(6)?,
+                    })
+                },
+            )
+            .optional()?;
+        Ok(row)
+    }
+
+    /// Insert or replace the catalog row for a freshly parsed book, keeping
+    /// the FTS index in step with the main table.
+    pub fn upsert(&self, bm: &BookMetadata, mtime: i64) -> Result<(), Box<dyn Error>> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO books (file, id, title, creator, publisher, description, filesize, mtime)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+             ON CONFLICT(file) DO UPDATE SET
+                id = excluded.id,
+                title = excluded.title,
+                creator = excluded.creator,
+                publisher = excluded.publisher,
+                description = excluded.description,
+                filesize = excluded.filesize,
+                mtime = excluded.mtime",
+            params![
+                bm.file,
+                bm.id,
+                bm.title,
+                bm.creator,
+                bm.publisher,
+                bm.description,
+                bm.filesize,
+                mtime
+            ],
+        )?;
+        conn.execute("DELETE FROM books_fts WHERE file = ?1", params![bm.file])?;
+        conn.execute(
+            "INSERT INTO books_fts (file, title, creator, description) VALUES (?1, ?2, ?3, ?4)",
+            params![bm.file, bm.title, bm.creator, bm.description],
+        )?;
+        Ok(())
+    }
+
+    /// Every indexed (id, file, filesize) triple, used to seed the in-memory
+    /// dedup map so duplicates are caught across runs, not just within one.
+    pub fn all_books(&self) -> Result<Vec<(i64, String, i64)>, Box<dyn Error>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT id, file, filesize FROM books")?;
+        let rows = stmt
+            .query_map([], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rows)
+    }
+
+    /// Remove the catalog entry for a file, eg. one whose `--prune-missing`
+    /// found no longer exists on disk.
+    pub fn remove(&self, file: &str) -> Result<(), Box<dyn Error>> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM books WHERE file = ?1", params![file])?;
+        conn.execute("DELETE FROM books_fts WHERE file = ?1", params![file])?;
+        Ok(())
+    }
+
+    /// Full-text search over title/creator/description, returning matching
+    /// file paths ranked by relevance.
+    pub fn search(&self, query: &str) -> Result<Vec<String>, Box<dyn Error>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt =
+            conn.prepare("SELECT file FROM books_fts WHERE books_fts MATCH ?1 ORDER BY rank")?;
+        let rows = stmt
+            .query_map(params![query], |r| r.get(0))?
+            .collect::<Result<Vec<String>, _>>()?;
+        Ok(rows)
+    }
+}
+
+#[cfg(test)]
+fn sample_book() -> crate::BookMetadata {
+    crate::BookMetadata {
+        id: 42,
+        title: Some("The Call of Cthulhu".to_string()),
+        description: Some("A story about an ancient cult".to_string()),
+        publisher: Some("Weird Tales".to_string()),
+        creator: Some("H.P. Lovecraft".to_string()),
+        file: "/books/cthulhu.epub".to_string(),
+        filesize: 1024,
+        mtime: 1_700_000_000,
+    }
+}
+
+#[test]
+fn upsert_then_lookup_round_trips_the_row() {
+    let catalog = Catalog::open(":memory:").unwrap();
+    let bm = sample_book();
+
+    assert!(catalog.lookup(&bm.file).unwrap().is_none());
+
+    catalog.upsert(&bm, bm.mtime).unwrap();
+
+    let row = catalog
+        .lookup(&bm.file)
+        .unwrap()
+        .expect("row should exist after upsert");
+    assert_eq!(row.id, bm.id);
+    assert_eq!(row.title, bm.title);
+    assert_eq!(row.creator, bm.creator);
+    assert_eq!(row.filesize, bm.filesize);
+    assert_eq!(row.mtime, bm.mtime);
+}
+
+#[test]
+fn upsert_replaces_the_existing_row_for_the_same_file() {
+    let catalog = Catalog::open(":memory:").unwrap();
+    let mut bm = sample_book();
+    catalog.upsert(&bm, bm.mtime).unwrap();
+
+    bm.title = Some("Updated Title".to_string());
+    bm.mtime += 1;
+    catalog.upsert(&bm, bm.mtime).unwrap();
+
+    let row = catalog.lookup(&bm.file).unwrap().unwrap();
+    assert_eq!(row.title, bm.title);
+    assert_eq!(row.mtime, bm.mtime);
+    assert_eq!(catalog.all_books().unwrap().len(), 1);
+}
+
+#[test]
+fn search_finds_books_by_title_and_creator() {
+    let catalog = Catalog::open(":memory:").unwrap();
+    let bm = sample_book();
+    catalog.upsert(&bm, bm.mtime).unwrap();
+
+    assert_eq!(catalog.search("cthulhu").unwrap(), vec![bm.file.clone()]);
+    assert_eq!(catalog.search("lovecraft").unwrap(), vec![bm.file.clone()]);
+    assert!(catalog.search("nonexistent").unwrap().is_empty());
+}
+
+#[test]
+fn remove_drops_the_row_from_both_tables() {
+    let catalog = Catalog::open(":memory:").unwrap();
+    let bm = sample_book();
+    catalog.upsert(&bm, bm.mtime).unwrap();
+
+    catalog.remove(&bm.file).unwrap();
+
+    assert!(catalog.lookup(&bm.file).unwrap().is_none());
+    assert!(catalog.search("cthulhu").unwrap().is_empty());
+    assert!(catalog.all_books().unwrap().is_empty());
+}
@@ -0,0 +1,85 @@
+use lingua::Language;
+use lingua::Language::*;
+use std::collections::HashSet;
+use std::error::Error;
+
+/// Parse a comma-separated list of BCP-47 language tags (eg. `"en,fr,de"`)
+/// into the `lingua::Language` variants a user wants to keep. Only the
+/// primary subtag is consulted - `en-US` and `en-GB` both mean `en`.
+pub fn parse_keep_langs(tags: &str) -> Result<HashSet<Language>, Box<dyn Error>> {
+    let langs: HashSet<Language> = tags
+        .split(',')
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .map(|tag| {
+            let primary = tag.split('-').next().unwrap_or(tag).to_lowercase();
+            language_for_tag(&primary).ok_or_else(|| -> Box<dyn Error> {
+                format!("unrecognised language tag: {}", tag).into()
+            })
+        })
+        .collect::<Result<_, _>>()?;
+
+    // An empty set would make is_kept_language() flag every book as
+    // foreign, the opposite of bkgrep's conservative default - treat
+    // "--keep-lang ''" the same as not passing the flag at all.
+    if langs.is_empty() {
+        return Ok(HashSet::from([English]));
+    }
+
+    Ok(langs)
+}
+
+/// Map an ISO 639-1 primary subtag to the `lingua` language it identifies.
+/// Covers a subset of the languages `lingua` supports; any other tag
+/// returns `None`.
+fn language_for_tag(tag: &str) -> Option<Language> {
+    Some(match tag {
+        "en" => English,
+        "fr" => French,
+        "de" => German,
+        "es" => Spanish,
+        "it" => Italian,
+        "pt" => Portuguese,
+        "nl" => Dutch,
+        "ru" => Russian,
+        "zh" => Chinese,
+        "ja" => Japanese,
+        "ko" => Korean,
+        "ar" => Arabic,
+        "pl" => Polish,
+        "sv" => Swedish,
+        "no" => Bokmal,
+        "da" => Danish,
+        "fi" => Finnish,
+        "el" => Greek,
+        "tr" => Turkish,
+        "cs" => Czech,
+        "hu" => Hungarian,
+        "ro" => Romanian,
+        "uk" => Ukrainian,
+        "he" => Hebrew,
+        "hi" => Hindi,
+        "vi" => Vietnamese,
+        "id" => Indonesian,
+        "th" => Thai,
+        _ => return None,
+    })
+}
+
+#[test]
+fn parses_known_tags_including_region_subtags() {
+    let langs = parse_keep_langs("en-US, fr , DE").unwrap();
+    assert_eq!(langs, HashSet::from([English, French, German]));
+}
+
+#[test]
+fn defaults_to_english_when_tag_list_is_empty() {
+    assert_eq!(parse_keep_langs("").unwrap(), HashSet::from([English]));
+    assert_eq!(parse_keep_langs(",, ").unwrap(), HashSet::from([English]));
+}
+
+#[test]
+fn rejects_unrecognised_tags() {
+    assert!(parse_keep_langs("xx").is_err());
+    assert!(parse_keep_langs("en,xx").is_err());
+}
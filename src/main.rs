@@ -1,4 +1,10 @@
+mod action;
+mod catalog;
+mod lang;
+mod opf;
 mod scanner;
+mod text;
+use action::{Action, ActionConfig};
 use clap::{Parser, Subcommand};
 use std::collections::hash_map::DefaultHasher;
 use std::collections::HashMap;
@@ -12,6 +18,7 @@ pub struct BookMetadata {
     creator: Option<String>,
     file: String,
     filesize: i64,
+    mtime: i64,
 }
 
 impl BookMetadata {
@@ -37,6 +44,15 @@ impl Hash for BookMetadata {
     }
 }
 
+#[derive(Subcommand, Debug)]
+enum Commands {
+    /// Query the catalog's full-text index of title/creator/description
+    Search {
+        /// Text to search for, eg. "bkgrep search lovecraft"
+        query: String,
+    },
+}
+
 /// Find epub that match specific patterns (or not).
 /// The file locations of epubs that match are written to std out.
 /// Intended to allow scanning a collection of epubs and listing all the duplicate and foreign epubs
@@ -44,7 +60,10 @@ impl Hash for BookMetadata {
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
 struct Cli {
-    /// Find epubs written in a foreign language ie. not english
+    #[command(subcommand)]
+    command: Option<Commands>,
+
+    /// Find epubs written in a language outside --keep-lang (english by default)
     #[arg(short, long, action)]
     find_foreign: bool,
 
@@ -55,10 +74,88 @@ struct Cli {
     /// Find epubs which are duplicates. Epubs with the same author, title and publisher are considered identical, only the smallest are reported as duplicates
     #[arg(short, long, action)]
     dups: bool,
+
+    /// Comma-separated BCP-47 language tags to keep; epubs confidently detected outside this set are flagged foreign
+    #[arg(short, long, default_value = "en")]
+    keep_lang: String,
+
+    /// Path to the persistent sqlite catalog used to avoid re-parsing unchanged epubs
+    #[arg(long, default_value = "bkgrep.db")]
+    catalog: String,
+
+    /// What to do with the losing copy of a detected duplicate
+    #[arg(long, value_enum, default_value = "print")]
+    action: Action,
+
+    /// Directory duplicates are moved to when --action trash is used
+    #[arg(long, default_value = "trash")]
+    trash_dir: String,
+
+    /// Required alongside --action trash/delete/hardlink before any file is actually touched
+    #[arg(long, action)]
+    confirm: bool,
+
+    /// Remove catalog entries whose files no longer exist on disk, then exit
+    #[arg(long, action)]
+    prune_missing: bool,
 }
 
 fn main() {
     let cli = Cli::parse();
-    let scanner = scanner::Scanner::new(cli.dir, cli.find_foreign);
-    scanner.scan_dirs();
+    let catalog = catalog::Catalog::open(&cli.catalog).unwrap_or_else(|e| {
+        eprintln!("Could not open catalog {}: {}", &cli.catalog, e);
+        std::process::exit(2);
+    });
+
+    if cli.prune_missing {
+        if let Err(e) = prune_missing(&catalog) {
+            eprintln!("Prune failed: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    match cli.command {
+        Some(Commands::Search { query }) => match catalog.search(&query) {
+            Ok(hits) => {
+                for hit in hits {
+                    println!("{}", hit);
+                }
+            }
+            Err(e) => {
+                eprintln!("Search failed: {}", e);
+                std::process::exit(1);
+            }
+        },
+        None => {
+            let keep_langs = lang::parse_keep_langs(&cli.keep_lang).unwrap_or_else(|e| {
+                eprintln!("Invalid --keep-lang: {}", e);
+                std::process::exit(2);
+            });
+            let action_config = ActionConfig {
+                action: cli.action,
+                trash_dir: cli.trash_dir,
+                confirm: cli.confirm,
+            };
+            let scanner =
+                scanner::Scanner::new(cli.dir, cli.find_foreign, keep_langs, action_config, catalog);
+            if let Err(e) = scanner.scan_dirs() {
+                eprintln!("Scan failed: {}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+}
+
+/// Walk every catalog row and drop the ones whose file no longer exists on
+/// disk, so the index doesn't accumulate ghost entries as a library is
+/// edited outside of bkgrep.
+fn prune_missing(catalog: &catalog::Catalog) -> Result<(), Box<dyn Error>> {
+    for (_, file, _) in catalog.all_books()? {
+        if !std::path::Path::new(&file).exists() {
+            catalog.remove(&file)?;
+            println!("PRUNED:{}", file);
+        }
+    }
+    Ok(())
 }
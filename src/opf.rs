@@ -0,0 +1,252 @@
+use quick_xml::events::Event;
+use quick_xml::reader::Reader;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use zip::ZipArchive;
+
+/// A `dc:creator` entry with role `aut` (or with no role at all), plus its
+/// sort name if the OPF supplied one via `file-as`/`opf:file-as`.
+pub struct Author {
+    pub name: String,
+    pub file_as: Option<String>,
+}
+
+/// Read the OPF package document out of an epub zip and return the list of
+/// `dc:creator` entries tagged with role `aut`, plus those with no role at
+/// all (which are treated as authors).
+///
+/// `unmangle_creator` is a reasonable guess when a book carries no sort name
+/// at all, but the OPF already spells it out unambiguously when `file_as`
+/// is `Some` - callers should prefer that over guessing.
+pub fn read_authors(book_loc: &str) -> Result<Vec<Author>, Box<dyn Error>> {
+    let file = File::open(book_loc)?;
+    let mut zip = ZipArchive::new(BufReader::new(file))?;
+
+    let container = read_zip_entry(&mut zip, "META-INF/container.xml")?;
+    let opf_path = find_rootfile(&container)?;
+    let opf = read_zip_entry(&mut zip, &opf_path)?;
+
+    Ok(parse_opf_authors(&opf))
+}
+
+fn read_zip_entry<R: std::io::Read + std::io::Seek>(
+    zip: &mut ZipArchive<R>,
+    name: &str,
+) -> Result<String, Box<dyn Error>> {
+    let mut entry = zip.by_name(name)?;
+    let mut contents = String::new();
+    entry.read_to_string(&mut contents)?;
+    Ok(contents)
+}
+
+/// Pull the `full-path` attribute of the first `<rootfile>` out of
+/// `META-INF/container.xml`.
+fn find_rootfile(container_xml: &str) -> Result<String, Box<dyn Error>> {
+    let mut reader = Reader::from_str(container_xml);
+    reader.config_mut().trim_text(true);
+    let mut buf = Vec::new();
+
+    loop {
+        match reader.read_event_into(&mut buf)? {
+            Event::Empty(e) | Event::Start(e) if e.local_name().as_ref() == b"rootfile" => {
+                for attr in e.attributes().flatten() {
+                    if attr.key.as_ref() == b"full-path" {
+                        return Ok(attr.unescape_value()?.into_owned());
+                    }
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+
+    Err("container.xml has no <rootfile full-path=...>".into())
+}
+
+struct Creator {
+    id: Option<String>,
+    name: String,
+    file_as: Option<String>,
+    role: Option<String>,
+}
+
+/// Walk the package document's `<metadata>` block, collecting `dc:creator`
+/// elements plus any EPUB3 `<meta refines="#id" property="file-as|role">`
+/// entries that refine them, then resolve each creator's sort name and role.
+fn parse_opf_authors(opf_xml: &str) -> Vec<Author> {
+    let mut reader = Reader::from_str(opf_xml);
+    reader.config_mut().trim_text(true);
+    let mut buf = Vec::new();
+
+    let mut creators: Vec<Creator> = Vec::new();
+    // id (without leading '#') -> (property, value) refinements
+    let mut refines: HashMap<String, HashMap<String, String>> = HashMap::new();
+
+    let mut in_creator: Option<(Option<String>, Option<String>, Option<String>)> = None;
+    let mut pending_refine: Option<(String, String)> = None;
+    let mut text = String::new();
+
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(e)) if e.local_name().as_ref() == b"creator" => {
+                let mut id = None;
+                let mut file_as = None;
+                let mut role = None;
+                for attr in e.attributes().flatten() {
+                    match attr.key.local_name().as_ref() {
+                        b"id" => id = Some(attr.unescape_value().unwrap_or_default().into_owned()),
+                        b"file-as" => {
+                            file_as = Some(attr.unescape_value().unwrap_or_default().into_owned())
+                        }
+                        b"role" => {
+                            role = Some(attr.unescape_value().unwrap_or_default().into_owned())
+                        }
+                        _ => {}
+                    }
+                }
+                in_creator = Some((id, file_as, role));
+                text.clear();
+            }
+            Ok(Event::End(e)) if e.local_name().as_ref() == b"creator" => {
+                if let Some((id, file_as, role)) = in_creator.take() {
+                    creators.push(Creator {
+                        id,
+                        name: text.trim().to_string(),
+                        file_as,
+                        role,
+                    });
+                }
+            }
+            Ok(Event::Empty(e)) | Ok(Event::Start(e)) if e.local_name().as_ref() == b"meta" => {
+                let mut refines_id = None;
+                let mut property = None;
+                for attr in e.attributes().flatten() {
+                    match attr.key.local_name().as_ref() {
+                        b"refines" => {
+                            refines_id = Some(
+                                attr.unescape_value()
+                                    .unwrap_or_default()
+                                    .trim_start_matches('#')
+                                    .to_string(),
+                            )
+                        }
+                        b"property" => {
+                            property = Some(attr.unescape_value().unwrap_or_default().into_owned())
+                        }
+                        _ => {}
+                    }
+                }
+                if let (Some(id), Some(prop)) = (refines_id, property) {
+                    pending_refine = Some((id, prop));
+                    text.clear();
+                } else {
+                    pending_refine = None;
+                }
+            }
+            Ok(Event::End(e)) if e.local_name().as_ref() == b"meta" => {
+                if let Some((id, prop)) = pending_refine.take() {
+                    refines
+                        .entry(id)
+                        .or_default()
+                        .insert(prop, text.trim().to_string());
+                }
+            }
+            Ok(Event::Text(e)) => {
+                text.push_str(&e.unescape().unwrap_or_default());
+            }
+            Ok(Event::Eof) => break,
+            Ok(_) => {}
+            Err(_) => break,
+        }
+        buf.clear();
+    }
+
+    creators
+        .into_iter()
+        .filter_map(|c| {
+            let refined = c.id.as_ref().and_then(|id| refines.get(id));
+            let role = refined
+                .and_then(|r| r.get("role").cloned())
+                .or(c.role.clone());
+            if role.as_deref().unwrap_or("aut") != "aut" {
+                return None;
+            }
+            let file_as = refined
+                .and_then(|r| r.get("file-as").cloned())
+                .or(c.file_as.clone());
+            Some(Author {
+                name: c.name,
+                file_as,
+            })
+        })
+        .collect()
+}
+
+#[test]
+fn find_rootfile_reads_full_path_from_container_xml() {
+    let container = r#"<?xml version="1.0"?>
+        <container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+            <rootfiles>
+                <rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/>
+            </rootfiles>
+        </container>"#;
+    assert_eq!(find_rootfile(container).unwrap(), "OEBPS/content.opf");
+}
+
+#[test]
+fn find_rootfile_errors_when_absent() {
+    let container = r#"<?xml version="1.0"?><container><rootfiles/></container>"#;
+    assert!(find_rootfile(container).is_err());
+}
+
+#[test]
+fn epub3_file_as_and_role_refine_the_creator_by_id() {
+    let opf = r#"<?xml version="1.0"?>
+        <package xmlns:dc="http://purl.org/dc/elements/1.1/">
+            <metadata>
+                <dc:creator id="creator01">H.P. Lovecraft</dc:creator>
+                <meta refines="#creator01" property="file-as">Lovecraft, H.P.</meta>
+                <meta refines="#creator01" property="role" scheme="marc:relators">aut</meta>
+                <dc:creator id="creator02">Jane Illustrator</dc:creator>
+                <meta refines="#creator02" property="role" scheme="marc:relators">ill</meta>
+            </metadata>
+        </package>"#;
+
+    let authors = parse_opf_authors(opf);
+    assert_eq!(authors.len(), 1);
+    assert_eq!(authors[0].name, "H.P. Lovecraft");
+    assert_eq!(authors[0].file_as.as_deref(), Some("Lovecraft, H.P."));
+}
+
+#[test]
+fn epub2_file_as_and_role_are_read_as_attributes_on_the_creator() {
+    let opf = r#"<?xml version="1.0"?>
+        <package xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:opf="http://www.idpf.org/2007/opf">
+            <metadata>
+                <dc:creator opf:file-as="Lovecraft, H.P." opf:role="aut">H.P. Lovecraft</dc:creator>
+            </metadata>
+        </package>"#;
+
+    let authors = parse_opf_authors(opf);
+    assert_eq!(authors.len(), 1);
+    assert_eq!(authors[0].name, "H.P. Lovecraft");
+    assert_eq!(authors[0].file_as.as_deref(), Some("Lovecraft, H.P."));
+}
+
+#[test]
+fn creator_without_file_as_keeps_the_raw_name() {
+    let opf = r#"<?xml version="1.0"?>
+        <package xmlns:dc="http://purl.org/dc/elements/1.1/">
+            <metadata>
+                <dc:creator>Lovecraft, H.P.</dc:creator>
+            </metadata>
+        </package>"#;
+
+    let authors = parse_opf_authors(opf);
+    assert_eq!(authors.len(), 1);
+    assert_eq!(authors[0].name, "Lovecraft, H.P.");
+    assert_eq!(authors[0].file_as, None);
+}
@@ -1,3 +1,7 @@
+use crate::action::{self, ActionConfig};
+use crate::catalog::Catalog;
+use crate::opf;
+use crate::text;
 use crate::BookMetadata;
 use epub::doc::EpubDoc;
 use itertools::Itertools;
@@ -9,14 +13,13 @@ use std::fs::File;
 use std::path::Path;
 use std::process;
 use std::sync::RwLock;
-use std::time::SystemTime;
+use std::time::{SystemTime, UNIX_EPOCH};
 use walkdir::{DirEntry, WalkDir};
 
-use lingua::Language::*;
+use lingua::Language;
 use lingua::LanguageDetector;
 use lingua::LanguageDetectorBuilder;
 use rand::prelude::*;
-use scraper::html::Html;
 //most essential book details for dedupping
 #[derive(Clone)]
 struct Book {
@@ -35,10 +38,24 @@ fn is_hidden(entry: &DirEntry) -> bool {
 pub struct Scanner {
     dirs: Vec<String>,
     detector: Option<LanguageDetector>,
+    keep_langs: HashSet<Language>,
+    action_config: ActionConfig,
+    catalog: Catalog,
 }
 
 impl Scanner {
-    pub fn new(dirs: Vec<String>, detect_lang: bool) -> Self {
+    pub fn new(
+        dirs: Vec<String>,
+        detect_lang: bool,
+        keep_langs: HashSet<Language>,
+        action_config: ActionConfig,
+        catalog: Catalog,
+    ) -> Self {
+        // Detect against every language lingua knows, not just the kept set -
+        // narrowing the candidate list would mean detect_language_of can only
+        // ever return a kept language, making is_kept_language's filter a
+        // no-op. Narrowing also makes ambiguous/short passages more likely to
+        // be misclassified as one of the few remaining options.
         let detector = if detect_lang {
             Some(
                 LanguageDetectorBuilder::from_all_languages()
@@ -49,7 +66,13 @@ impl Scanner {
             None
         };
 
-        Scanner { dirs, detector }
+        Scanner {
+            dirs,
+            detector,
+            keep_langs,
+            action_config,
+            catalog,
+        }
     }
 
     pub fn scan_dirs(&self) -> Result<(), Box<dyn std::error::Error>> {
@@ -61,7 +84,12 @@ impl Scanner {
         }
 
         // all books seen so far. For now store the location and fngers crossed don't run out of memory
-        let seen_books: RwLock<HashMap<i64, Book>> = std::sync::RwLock::new(HashMap::new());
+        // seeded from the catalog so duplicates are caught across runs, not just within one
+        let mut seen = HashMap::new();
+        for (id, location, size) in self.catalog.all_books()? {
+            seen.insert(id, Book { location, size });
+        }
+        let seen_books: RwLock<HashMap<i64, Book>> = std::sync::RwLock::new(seen);
         let mut book_batch = vec![];
 
         for dir in &self.dirs {
@@ -100,29 +128,60 @@ impl Scanner {
     fn process_batch(&self, seen_books: &RwLock<HashMap<i64, Book>>, book_batch: &Vec<String>) {
         book_batch
             .par_iter()
-            .map(|book_path| match parse_epub(book_path) {
+            .map(|book_path| match self.parse_epub_cached(book_path) {
                 Ok(bm) => {
-                    if self.is_english(&bm) {
+                    if self.is_kept_language(&bm) {
                         let new_bk = Book {
                             location: book_path.clone(),
                             size: bm.filesize,
                         };
-                        if !seen_books.read().unwrap().contains_key(&bm.id) {
-                            seen_books.write().unwrap().insert(bm.id, new_bk);
-                            Some(bm)
-                        } else {
-                            //DUPLICATE DETECTED
-                            let seen_unlocked = seen_books.read().unwrap();
-                            let old_bk = seen_unlocked.get(&bm.id).unwrap().clone();
-                            drop(seen_unlocked);
-                            if Self::better_dup(&old_bk, &new_bk) {
-                                println!("DUP:{}", old_bk.location);
-                                seen_books.write().unwrap().insert(bm.id, new_bk);
-                            } else {
-                                println!("DUP:{}", new_bk.location);
+                        // Decide the winner and record it under the write lock
+                        // so two copies of the same book landing in one batch
+                        // can't compute inconsistent winner/loser pairs - but
+                        // the lock is released before the file action runs, so
+                        // the rename/delete/hardlink itself isn't serialized
+                        // against the rest of the batch. This is race-free
+                        // because a decision only ever touches the file it
+                        // just named loser, and a loser is dropped from (or
+                        // never entered into) `seen`, so no file is named
+                        // loser twice.
+                        let duplicate = {
+                            let mut seen = seen_books.write().unwrap();
+                            match seen.get(&bm.id).cloned() {
+                                None => {
+                                    seen.insert(bm.id, new_bk);
+                                    None
+                                }
+                                Some(old_bk) => {
+                                    //DUPLICATE DETECTED
+                                    let new_wins = Self::better_dup(&old_bk, &new_bk);
+                                    if new_wins {
+                                        seen.insert(bm.id, new_bk.clone());
+                                    }
+                                    Some(if new_wins {
+                                        (new_bk, old_bk)
+                                    } else {
+                                        (old_bk, new_bk)
+                                    })
+                                }
                             }
+                        };
 
-                            None
+                        match duplicate {
+                            None => Some(bm),
+                            Some((winner, loser)) => {
+                                if let Err(e) = action::resolve_duplicate(
+                                    &winner.location,
+                                    &loser.location,
+                                    &self.action_config,
+                                ) {
+                                    eprintln!(
+                                        "Could not action duplicate {}: {}",
+                                        loser.location, e
+                                    );
+                                }
+                                None
+                            }
                         }
                     } else {
                         println!("FRN:{}", book_path);
@@ -140,10 +199,10 @@ impl Scanner {
             .collect::<Vec<BookMetadata>>();
     }
 
-    //the potential issue here is there's a difference between "yes tis is definitely english" and "this is definitely NOT english"
+    //the potential issue here is there's a difference between "yes tis is definitely a kept language" and "this is definitely NOT a kept language"
     //books with eg ambiguous title and no description won't be detected!
     //That's why we must detect using using ALL languages
-    fn is_english(&self, bm: &BookMetadata) -> bool {
+    fn is_kept_language(&self, bm: &BookMetadata) -> bool {
         if let Some(detector) = &self.detector {
             if bm.description.as_ref().is_some_and(|s| s.len() > 50) {
                 match detector.detect_language_of(
@@ -151,35 +210,16 @@ impl Scanner {
                         + " "
                         + bm.description.as_ref().unwrap_or(&"".to_string()),
                 ) {
-                    Some(English) => true,
-                    Some(_) => false,
+                    Some(lang) => self.keep_langs.contains(&lang),
                     None => true,
                 }
             } else {
                 //not enough information to be sure - inspect inside the book at a random point
-                //this is all prettyugly and hurried :/
                 let mut doc = EpubDoc::new(&bm.file).unwrap();
-                let mut content = String::new();
-                add_content(&mut doc, &mut content);
-                add_content(&mut doc, &mut content);
-                add_content(&mut doc, &mut content);
-                let mut cleaned = String::new();
-                let mut tref = String::new();
+                let prose = collect_prose(&mut doc);
 
-                let fragdoc = Html::parse_fragment(&content);
-                for node in fragdoc.tree {
-                    cleaned.push_str(match node {
-                        scraper::node::Node::Text(text) => {
-                            tref = text.text.to_string();
-                            &tref
-                        }
-                        _ => "",
-                    });
-                }
-
-                match detector.detect_language_of(cleaned) {
-                    Some(English) => true,
-                    Some(_) => false,
+                match detector.detect_language_of(prose) {
+                    Some(lang) => self.keep_langs.contains(&lang),
                     None => true,
                 }
             }
@@ -188,6 +228,44 @@ impl Scanner {
         }
     }
 
+    /// Parse `book_path`, skipping the epub entirely when the catalog already
+    /// has a row whose stored mtime and size match the file on disk.
+    fn parse_epub_cached(&self, book_path: &str) -> Result<BookMetadata, Box<dyn Error>> {
+        // Canonicalize up front: the catalog is keyed on the canonical path
+        // (that's what `upsert` stores), but WalkDir yields paths relative to
+        // whatever --dir was passed (eg. "./foo.epub"), so looking up the raw
+        // book_path would never hit and every rescan would re-parse the epub.
+        let file = Path::new(book_path).canonicalize()?;
+        let file = file.display().to_string();
+
+        let fs_meta = fs::metadata(&file)?;
+        let mtime = fs_meta
+            .modified()?
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs() as i64;
+        let filesize = fs_meta.len() as i64;
+
+        if let Some(row) = self.catalog.lookup(&file)? {
+            if row.mtime == mtime && row.filesize == filesize {
+                return Ok(BookMetadata {
+                    id: row.id,
+                    title: row.title,
+                    description: row.description,
+                    publisher: row.publisher,
+                    creator: row.creator,
+                    file,
+                    filesize: row.filesize,
+                    mtime: row.mtime,
+                });
+            }
+        }
+
+        let bm = parse_epub(&file, mtime)?;
+        self.catalog.upsert(&bm, mtime)?;
+        Ok(bm)
+    }
+
     fn better_dup(old: &Book, new: &Book) -> bool {
         if new.size > old.size {
             true
@@ -197,43 +275,80 @@ impl Scanner {
     }
 }
 
-fn add_content(doc: &mut EpubDoc<std::io::BufReader<File>>, content: &mut String) {
-    let rand_page = rand::thread_rng().gen_range(0..doc.get_num_pages());
-    doc.set_current_page(rand_page);
-    content.push_str(" ");
-    content.push_str(
-        doc.get_current_str()
-            .unwrap_or(("".to_string(), "".to_string()))
-            .0
-            .as_ref(),
-    );
+const PROSE_TARGET_LEN: usize = 300;
+
+/// Gather a few hundred characters of clean prose for language detection,
+/// starting at a random page and walking forward so a run of front-matter
+/// pages doesn't get the final word - pages with real paragraph text are
+/// preferred over heading-only pages (title page, copyright, TOC).
+fn collect_prose(doc: &mut EpubDoc<std::io::BufReader<File>>) -> String {
+    let num_pages = doc.get_num_pages();
+    if num_pages == 0 {
+        return String::new();
+    }
+    let start = rand::thread_rng().gen_range(0..num_pages);
+
+    let mut prose = String::new();
+    let mut fallback = String::new();
+    for offset in 0..num_pages {
+        doc.set_current_page((start + offset) % num_pages);
+        let Ok((page, _)) = doc.get_current_str() else {
+            continue;
+        };
+        let extracted = text::extract_text(&page);
+        if extracted.has_paragraph {
+            prose.push(' ');
+            prose.push_str(&extracted.text);
+        } else {
+            fallback.push(' ');
+            fallback.push_str(&extracted.text);
+        }
+        if prose.len() >= PROSE_TARGET_LEN {
+            break;
+        }
+    }
+
+    if prose.is_empty() {
+        fallback
+    } else {
+        prose
+    }
 }
-fn parse_epub(book_loc: &str) -> Result<BookMetadata, Box<dyn Error>> {
+/// `book_loc` must already be canonicalized - callers key the catalog on it,
+/// so re-deriving a (possibly different) canonical path here would break
+/// that invariant.
+fn parse_epub(book_loc: &str, mtime: i64) -> Result<BookMetadata, Box<dyn Error>> {
     let mut doc = EpubDoc::new(&book_loc)?;
     let metadata = fs::metadata(&book_loc)?;
 
-    let file = match Path::new(&book_loc).canonicalize() {
-        Ok(f) => f.display().to_string(),
-        Err(e) => {
-            eprintln!("Could not canonicalize {}", &e);
-            return Err(Box::new(e));
-        }
-    };
-
     let mut bm = BookMetadata {
         id: 0i64,
         title: get_first_fd("title", &doc.metadata),
         description: get_first_fd("description", &doc.metadata),
         publisher: get_first_fd("publisher", &doc.metadata),
-        creator: get_first_fd("creator", &doc.metadata).map(unmangle_creator),
-        file,
+        creator: read_creator(book_loc)
+            .or(get_first_fd("creator", &doc.metadata).map(unmangle_creator)),
+        file: book_loc.to_string(),
         filesize: metadata.len() as i64,
+        mtime,
     };
 
     bm.id = bm.hash_md();
     Ok(bm)
 }
 
+/// Prefer the OPF's explicit `file-as`/`opf:file-as` sort name for the first
+/// `aut` creator, falling back to the raw `dc:creator` text when the OPF
+/// didn't supply one (and to `None` entirely when the OPF can't be read, eg.
+/// a malformed zip). Either way the result goes through `unmangle_creator` -
+/// `file-as` is still "Last, First" order, so without it a book with a
+/// `file-as` and a book without one (but the same raw creator text) would end
+/// up with differently-spelled creators instead of collapsing to one.
+fn read_creator(book_loc: &str) -> Option<String> {
+    let author = opf::read_authors(book_loc).ok()?.into_iter().next()?;
+    Some(unmangle_creator(author.file_as.unwrap_or(author.name)))
+}
+
 fn get_first_fd(mdfield: &str, md: &HashMap<String, Vec<String>>) -> Option<String> {
     match md.get(mdfield) {
         Some(vec) => Some(vec.get(0).unwrap().clone()),
@@ -0,0 +1,140 @@
+use quick_xml::events::Event;
+use quick_xml::reader::Reader;
+
+/// Elements whose content is never prose and must not leak into language
+/// detection - stylesheets, scripts, nav chrome and embedded markup.
+const SKIP_ELEMENTS: &[&[u8]] = &[b"style", b"script", b"nav", b"iframe", b"svg"];
+
+/// Plain text pulled out of a page of epub (x)html, plus whether any of it
+/// came from a `<p>` - used to prefer real prose over heading-only
+/// front-matter pages when picking what to feed the language detector.
+pub struct ExtractedText {
+    pub text: String,
+    pub has_paragraph: bool,
+}
+
+/// Stream `page` and collect character data outside of [`SKIP_ELEMENTS`],
+/// decoding entities as we go. `<h1>`-`<h6>` headings are kept (they're
+/// still readable text) but don't count as paragraph prose.
+pub fn extract_text(page: &str) -> ExtractedText {
+    let mut reader = Reader::from_str(page);
+    reader.config_mut().check_end_names = false;
+
+    let mut text = String::new();
+    let mut has_paragraph = false;
+    let mut skip_depth: Vec<Vec<u8>> = Vec::new();
+    let mut in_paragraph = false;
+    let mut buf = Vec::new();
+
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(e)) => {
+                let name = local_name(e.name().as_ref());
+                if SKIP_ELEMENTS.contains(&name) {
+                    skip_depth.push(name.to_vec());
+                } else if name == b"p" {
+                    in_paragraph = true;
+                }
+            }
+            Ok(Event::End(e)) => {
+                let name = local_name(e.name().as_ref());
+                if skip_depth.last().map(|s| s.as_slice() == name).unwrap_or(false) {
+                    skip_depth.pop();
+                } else if name == b"p" {
+                    in_paragraph = false;
+                }
+            }
+            Ok(Event::Text(e)) => {
+                if skip_depth.is_empty() {
+                    if let Ok(decoded) = e.unescape_with(resolve_entity) {
+                        text.push_str(&decoded);
+                        text.push(' ');
+                        if in_paragraph && !decoded.trim().is_empty() {
+                            has_paragraph = true;
+                        }
+                    }
+                }
+            }
+            Ok(Event::CData(e)) => {
+                if skip_depth.is_empty() {
+                    if let Ok(decoded) = String::from_utf8(e.into_inner().to_vec()) {
+                        text.push_str(&decoded);
+                        text.push(' ');
+                    }
+                }
+            }
+            Ok(Event::Eof) => break,
+            Ok(_) => {}
+            Err(_) => break,
+        }
+        buf.clear();
+    }
+
+    ExtractedText { text, has_paragraph }
+}
+
+/// quick_xml only resolves the five XML-builtin entities; epub content is
+/// HTML and routinely carries a handful more, so fill in the common ones.
+fn resolve_entity(entity: &str) -> Option<&str> {
+    match entity {
+        "nbsp" => Some("\u{a0}"),
+        "mdash" => Some("\u{2014}"),
+        "ndash" => Some("\u{2013}"),
+        "hellip" => Some("\u{2026}"),
+        "copy" => Some("\u{a9}"),
+        "ldquo" => Some("\u{201c}"),
+        "rdquo" => Some("\u{201d}"),
+        "lsquo" => Some("\u{2018}"),
+        "rsquo" => Some("\u{2019}"),
+        _ => None,
+    }
+}
+
+fn local_name(qname: &[u8]) -> &[u8] {
+    match qname.iter().position(|&b| b == b':') {
+        Some(i) => &qname[i + 1..],
+        None => qname,
+    }
+}
+
+#[test]
+fn excludes_style_script_and_nav_content() {
+    let page = r#"<html><head><style>body { color: red; }</style>
+        <script>alert("hi");</script></head>
+        <body><nav>Home | About</nav><p>Real prose lives here.</p></body></html>"#;
+
+    let extracted = extract_text(page);
+    assert!(extracted.text.contains("Real prose lives here."));
+    assert!(!extracted.text.contains("color: red"));
+    assert!(!extracted.text.contains("alert"));
+    assert!(!extracted.text.contains("Home"));
+}
+
+#[test]
+fn excludes_iframe_and_svg_content() {
+    let page = r#"<body><iframe>should not appear</iframe>
+        <svg><text>should not appear either</text></svg>
+        <p>Kept paragraph.</p></body>"#;
+
+    let extracted = extract_text(page);
+    assert!(extracted.text.contains("Kept paragraph."));
+    assert!(!extracted.text.contains("should not appear"));
+}
+
+#[test]
+fn decodes_common_html_entities() {
+    let page = "<p>cat&nbsp;&amp;&nbsp;mouse&mdash;forever&hellip;</p>";
+    let extracted = extract_text(page);
+    assert!(extracted.text.contains("cat\u{a0}&\u{a0}mouse\u{2014}forever\u{2026}"));
+}
+
+#[test]
+fn paragraph_text_is_flagged_but_headings_are_not() {
+    let heading_only = extract_text("<h1>Chapter One</h1>");
+    assert!(!heading_only.has_paragraph);
+    assert!(heading_only.text.contains("Chapter One"));
+
+    let with_paragraph = extract_text("<h1>Chapter One</h1><p>It was a dark and stormy night.</p>");
+    assert!(with_paragraph.has_paragraph);
+}
+